@@ -1,11 +1,228 @@
 use std::time::Duration;
 use macroquad::prelude::*;
 use macroquad::time::get_time;
+use macroquad::experimental::collections::storage;
+use macroquad::experimental::coroutines::start_coroutine;
+#[cfg(feature = "audio")]
+use macroquad::audio::{self, PlaySoundParams, Sound};
+use macroquad_particles::{ColorCurve, Emitter, EmitterConfig};
+
+/// All-time high score, kept in `storage` and (on desktop) mirrored to a
+/// file under the user's config dir so it survives restarts.
+struct HighScore(u32);
+
+#[cfg(not(target_arch = "wasm32"))]
+fn high_score_dir() -> Option<std::path::PathBuf> {
+    let mut dir = std::path::PathBuf::from(std::env::var_os("HOME")?);
+    dir.push(".config/space_command");
+    Some(dir)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_high_score() -> u32 {
+    high_score_dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join("highscore.txt")).ok())
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(0)
+}
+#[cfg(target_arch = "wasm32")]
+fn load_high_score() -> u32 {
+    0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_high_score(score: u32) {
+    if let Some(dir) = high_score_dir() {
+        let _ = std::fs::create_dir_all(&dir);
+        let _ = std::fs::write(dir.join("highscore.txt"), score.to_string());
+    }
+}
+#[cfg(target_arch = "wasm32")]
+fn save_high_score(_score: u32) {}
+
+/// Updates the in-memory and on-disk high score if `score` beats it.
+fn record_score(score: u32) {
+    let mut high_score = storage::get_mut::<HighScore>();
+    if score > high_score.0 {
+        high_score.0 = score;
+        save_high_score(high_score.0);
+    }
+}
+
+fn get_high_score() -> u32 {
+    storage::get::<HighScore>().0
+}
+
+const STARFIELD_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+varying lowp vec2 uv;
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}
+"#;
+
+const STARFIELD_FRAGMENT_SHADER: &str = r#"#version 100
+precision lowp float;
+varying vec2 uv;
+uniform float iTime;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+void main() {
+    vec2 p = uv * vec2(40.0, 24.0) + vec2(0.0, iTime * 1.5);
+    vec2 cell = floor(p);
+    float star = step(0.985, hash(cell));
+    float twinkle = 0.5 + 0.5 * sin(iTime * 3.0 + hash(cell) * 6.2831);
+    gl_FragColor = vec4(vec3(star * twinkle), 1.0);
+}
+"#;
+
+/// Assets shared across the whole game, loaded once at startup and parked in
+/// `storage` so any part of the game can reach them without being threaded
+/// through every function signature.
+struct Resources {
+    font: Font,
+    starfield_material: Material,
+    #[cfg(feature = "audio")]
+    laser_sound: Sound,
+    #[cfg(feature = "audio")]
+    explosion_sound: Sound,
+    #[cfg(feature = "audio")]
+    theme_music: Sound,
+}
+
+impl Resources {
+    async fn load() -> Result<Resources, macroquad::Error> {
+        let font = load_ttf_font_from_bytes(include_bytes!("../assets/fonts/Geoplace-Bold.ttf"))?;
+
+        let starfield_material = load_material(
+            ShaderSource::Glsl {
+                vertex: STARFIELD_VERTEX_SHADER,
+                fragment: STARFIELD_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![UniformDesc::new("iTime", UniformType::Float1)],
+                ..Default::default()
+            },
+        )?;
+
+        #[cfg(feature = "audio")]
+        let laser_sound = audio::load_sound_from_bytes(include_bytes!("../assets/sounds/laser.wav")).await?;
+        #[cfg(feature = "audio")]
+        let explosion_sound = audio::load_sound_from_bytes(include_bytes!("../assets/sounds/explosion.wav")).await?;
+        #[cfg(feature = "audio")]
+        let theme_music = audio::load_sound_from_bytes(include_bytes!("../assets/sounds/theme.wav")).await?;
+
+        Ok(Resources {
+            font,
+            starfield_material,
+            #[cfg(feature = "audio")]
+            laser_sound,
+            #[cfg(feature = "audio")]
+            explosion_sound,
+            #[cfg(feature = "audio")]
+            theme_music,
+        })
+    }
+}
+
+#[cfg(feature = "audio")]
+fn play_laser_sound() {
+    let resources = storage::get::<Resources>();
+    audio::play_sound_once(&resources.laser_sound);
+}
+#[cfg(not(feature = "audio"))]
+fn play_laser_sound() {}
+
+#[cfg(feature = "audio")]
+fn play_explosion_sound() {
+    let resources = storage::get::<Resources>();
+    audio::play_sound_once(&resources.explosion_sound);
+}
+#[cfg(not(feature = "audio"))]
+fn play_explosion_sound() {}
+
+#[cfg(feature = "audio")]
+fn play_theme_music() {
+    let resources = storage::get::<Resources>();
+    // Stop any copy already looping before starting a new one, so repeated
+    // MainMenu -> Playing transitions don't stack the theme on top of itself.
+    audio::stop_sound(&resources.theme_music);
+    audio::play_sound(&resources.theme_music, PlaySoundParams { looped: true, volume: 1. });
+}
+#[cfg(not(feature = "audio"))]
+fn play_theme_music() {}
+
+#[cfg(feature = "audio")]
+fn stop_theme_music() {
+    let resources = storage::get::<Resources>();
+    audio::stop_sound(&resources.theme_music);
+}
+#[cfg(not(feature = "audio"))]
+fn stop_theme_music() {}
+
+fn draw_main_text(text: &str, x: f32, y: f32) {
+    let resources = storage::get::<Resources>();
+    draw_text_ex(text, x, y, TextParams {
+        font_size: 20,
+        font_scale: 1.0,
+        font_scale_aspect: 1.0,
+        font: Some(&resources.font),
+        color: WHITE,
+        rotation: 0.,
+    });
+}
+
+fn draw_sub_text(text: &str, x: f32, y: f32) {
+    let resources = storage::get::<Resources>();
+    draw_text_ex(text, x, y, TextParams {
+        font_size: 15,
+        font_scale: 1.0,
+        font_scale_aspect: 1.0,
+        font: Some(&resources.font),
+        color: GRAY,
+        rotation: 0.,
+    });
+}
+
+fn draw_starfield() {
+    let resources = storage::get::<Resources>();
+    resources.starfield_material.set_uniform("iTime", get_time() as f32);
+    gl_use_material(&resources.starfield_material);
+    draw_rectangle(0., 0., screen_width(), screen_height(), WHITE);
+    gl_use_default_material();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GameState {
+    MainMenu,
+    Playing,
+    Paused,
+    GameOver,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Base {
     position: Vec2,
     color: Color,
+    ammo: u32,
+}
+
+impl Base {
+    fn new(position: Vec2, ammo: u32) -> Self {
+        Base {
+            position,
+            color: SKYBLUE,
+            ammo,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -44,9 +261,12 @@ impl Bullet {
         }
     }
 
-    fn explode(&mut self, color: Color, size: f32, duration: Duration, flash: bool) {
+    /// Detonates the bullet, returning `true` if this call actually spawned a
+    /// new explosion. Returns `false` (and does nothing) if the bullet had
+    /// already detonated, so callers know not to re-trigger explosion effects.
+    fn explode(&mut self, color: Color, size: f32, duration: Duration, flash: bool) -> bool {
         if self.exploding || self.exploded {
-            return;
+            return false;
         }
 
         self.explosion = Some(Explosion {
@@ -58,45 +278,162 @@ impl Bullet {
             flash,
         });
         self.exploding = true;
+        true
     }
 
-    pub fn get_explosion_spawn_time(&self) -> f64 {
-        if self.explosion.is_none() {
+    fn at_target(&self) -> bool {
+        (self.position - self.target).length() < 5.
+    }
+}
+
+impl Explosion {
+    /// Radius at the current time: grows from 0 to `size` over the first
+    /// half of `duration`, then shrinks back to 0 over the second half.
+    fn current_radius(&self) -> f32 {
+        let half = self.duration.as_secs_f64() / 2.;
+        let elapsed = get_time() - self.spawn_time;
+        if elapsed <= 0. || half <= 0. {
             return 0.;
         }
-        self.explosion.unwrap().spawn_time
+        if elapsed <= half {
+            self.size * (elapsed / half) as f32
+        } else {
+            self.size * (1. - ((elapsed - half) / half) as f32).max(0.)
+        }
     }
 
-    fn at_target(&self) -> bool {
-        (self.position - self.target).length() < 5.
+    fn is_expired(&self) -> bool {
+        get_time() - self.spawn_time > self.duration.as_secs_f64()
+    }
+
+    fn emitter_config(&self) -> EmitterConfig {
+        EmitterConfig {
+            one_shot: true,
+            emitting: true,
+            lifetime: 0.5,
+            lifetime_randomness: 0.3,
+            amount: 40,
+            initial_direction_spread: std::f32::consts::TAU,
+            initial_velocity: 120.,
+            initial_velocity_randomness: 0.8,
+            size: 4.,
+            size_randomness: 0.3,
+            gravity: vec2(0., 0.),
+            colors_curve: ColorCurve {
+                start: self.color,
+                mid: Color::new(self.color.r, self.color.g, self.color.b, 0.5),
+                end: Color::new(self.color.r, self.color.g, self.color.b, 0.),
+            },
+            ..Default::default()
+        }
     }
 }
 
-impl Explosion {
-    pub fn get_spawn_time(&self) -> f64 {
-        self.spawn_time
+/// An explosion's gameplay data paired with the particle burst drawing it.
+struct ActiveExplosion {
+    data: Explosion,
+    emitter: Emitter,
+}
+
+impl ActiveExplosion {
+    fn new(data: Explosion) -> Self {
+        ActiveExplosion {
+            emitter: Emitter::new(data.emitter_config()),
+            data,
+        }
     }
 }
 
-#[derive(Clone, Debug)]
 struct Game {
-    started: bool,
-    font: Font,
+    state: GameState,
     fullscreen: bool,
+    escape_consumed: bool,
     window_size: Vec2,
     difficulty: Difficulty,
     grid: bool,
+    starfield: bool,
+    bullets: Vec<Bullet>,
+    time_since_spawn: f32,
+    bases: Vec<Base>,
+    explosions: Vec<ActiveExplosion>,
+    score: u32,
+    missiles_spawned: u32,
 }
 
 impl Game {
-    async fn new(font_bytes: &[u8]) -> Self {
+    async fn new() -> Self {
         Game {
-            started: false,
-            font: load_ttf_font_from_bytes(font_bytes).expect("Failed to load font"),
+            state: GameState::MainMenu,
             fullscreen: false,
+            escape_consumed: false,
             window_size: vec2(screen_width(), screen_height()),
             difficulty: Difficulty::default(),
             grid: false,
+            starfield: true,
+            bullets: Vec::new(),
+            time_since_spawn: 0.,
+            bases: Vec::new(),
+            explosions: Vec::new(),
+            score: 0,
+            missiles_spawned: 0,
+        }
+    }
+
+    const BASE_COUNT: u32 = 3;
+
+    fn spawn_bases(&mut self) {
+        let ammo = self.difficulty.get_missile_rounds();
+        let spacing = screen_width() / (Self::BASE_COUNT + 1) as f32;
+        self.bases = (1..=Self::BASE_COUNT)
+            .map(|i| Base::new(vec2(spacing * i as f32, screen_height() - 20.), ammo))
+            .collect();
+    }
+
+    /// Restocks ammo on the bases that survived the round, without
+    /// resurrecting ones an unintercepted missile already destroyed.
+    fn resupply_bases(&mut self) {
+        let ammo = self.difficulty.get_missile_rounds();
+        for base in &mut self.bases {
+            base.ammo = ammo;
+        }
+    }
+
+    fn draw_bases(&self) {
+        for base in &self.bases {
+            draw_triangle(
+                vec2(base.position.x - 10., base.position.y + 10.),
+                vec2(base.position.x + 10., base.position.y + 10.),
+                vec2(base.position.x, base.position.y - 10.),
+                base.color,
+            );
+            draw_text(base.ammo.to_string(), base.position.x - 4., base.position.y + 24., 16., WHITE);
+        }
+    }
+
+    fn nearest_base_with_ammo(&mut self, target: Vec2) -> Option<&mut Base> {
+        self.bases
+            .iter_mut()
+            .filter(|base| base.ammo > 0)
+            .min_by(|a, b| {
+                (a.position - target).length_squared()
+                    .partial_cmp(&(b.position - target).length_squared())
+                    .unwrap()
+            })
+    }
+
+    /// Destroys whichever base is closest to an unintercepted missile impact.
+    fn destroy_nearest_base(&mut self, impact: Vec2) {
+        if let Some((index, _)) = self
+            .bases
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                (a.position - impact).length_squared()
+                    .partial_cmp(&(b.position - impact).length_squared())
+                    .unwrap()
+            })
+        {
+            self.bases.remove(index);
         }
     }
 
@@ -110,73 +447,264 @@ impl Game {
 
         }
     }
-    
-    async fn draw_menu(&mut self){
-        let font = &self.font.clone();
-        let main_text_params = TextParams {
-            font_size: 20,
-            font_scale: 1.0,
-            font_scale_aspect: 1.0,
-            font: Some(font),
-            color: WHITE,
-            rotation: 0.,
-        };
-        let sub_text_params = TextParams {
-            font_size: 15,
-            font_scale: 1.0,
-            font_scale_aspect: 1.0,
-            font: Some(font),
-            color: GRAY,
-            rotation: 0.,
-        };
-        loop {
-            self.process_input().await;
-            if self.grid {
-                self.draw_grid().await;
-            }
-            else {
-                clear_background(BLACK);
+
+    async fn draw_main_menu(&mut self) {
+        if self.starfield {
+            draw_starfield();
+        } else {
+            clear_background(BLACK);
+        }
+        if self.grid {
+            self.draw_grid().await;
+        }
+
+        draw_main_text("Space Command", screen_width() / 2. - 128., screen_height() / 2. - 50.);
+        draw_main_text("Press Space to Start", screen_width() / 2. - 158., screen_height());
+        draw_sub_text("Press Ctrl + R to Reset", screen_width() / 2. - 128., screen_height() - 50.);
+        draw_sub_text(&format!("High Score: {}", get_high_score()), screen_width() / 2. - 158., screen_height() / 2. + 30.);
+
+        if is_key_pressed(KeyCode::Space) {
+            self.bullets.clear();
+            self.explosions.clear();
+            self.time_since_spawn = 0.;
+            self.missiles_spawned = 0;
+            self.score = 0;
+            self.spawn_bases();
+            self.state = GameState::Playing;
+            play_theme_music();
+        }
+        if is_key_pressed(KeyCode::R) && is_key_down(KeyCode::LeftControl) {
+            self.difficulty.reset();
+        }
+        if is_key_pressed(KeyCode::I) && is_key_down(KeyCode::LeftControl) {
+            self.difficulty.increase_difficulty();
+        }
+    }
+
+    async fn update_playing(&mut self) {
+        if self.starfield {
+            draw_starfield();
+        } else {
+            clear_background(BLACK);
+        }
+
+        if (is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape)) && !self.escape_consumed {
+            self.state = GameState::Paused;
+            return;
+        }
+
+        // Ctrl + G forces a round to end, for exercising the game-over screen.
+        if is_key_pressed(KeyCode::G) && is_key_down(KeyCode::LeftControl) {
+            record_score(self.score);
+            stop_theme_music();
+            self.state = GameState::GameOver;
+            return;
+        }
+
+        self.time_since_spawn += get_frame_time();
+        if self.time_since_spawn > self.difficulty.get_missile_spawn_rate()
+            && self.missiles_spawned < self.difficulty.get_missile_rounds()
+        {
+            let source = vec2(rand::gen_range(0., screen_height()), 0.);
+            let target = vec2(rand::gen_range(0., screen_width()), screen_height());
+            let velocity = (target - source).normalize() * self.difficulty.get_missile_speed();
+            let bullet = Bullet::new(source, target, velocity);
+            self.bullets.push(bullet);
+            self.missiles_spawned += 1;
+            self.time_since_spawn = 0.;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (mouse_x, mouse_y) = mouse_position();
+            let target = vec2(mouse_x, mouse_y);
+            if let Some(base) = self.nearest_base_with_ammo(target) {
+                let source = base.position;
+                base.ammo -= 1;
+                let velocity = (target - source).normalize() * self.difficulty.get_missile_speed() * 2.;
+                let mut interceptor = Bullet::new(source, target, velocity);
+                interceptor.color = GREEN;
+                self.bullets.push(interceptor);
+                play_laser_sound();
             }
-            draw_text_ex("Space Command", screen_width() / 2. - 128., screen_height() / 2. - 50., main_text_params.clone());
-            draw_text_ex("Press Space to Start", screen_width() / 2. - 158., screen_height(), main_text_params.clone());
-            draw_text_ex("Press Ctrl + R to Reset", screen_width() / 2. - 128., screen_height() - 50., sub_text_params.clone());
+        }
+
+        self.draw_bases();
+        self.draw_bullets();
 
-            if is_key_pressed(KeyCode::Space) {
-                break;
+        // check if any bullets have reached their target
+        let mut unintercepted_impacts = Vec::new();
+        for bullet in &mut self.bullets {
+            if bullet.at_target() {
+                let color = bullet.color;
+                if bullet.explode(color, self.difficulty.get_explosion_size(), Duration::from_secs(1), false) {
+                    play_explosion_sound();
+                    // A RED missile detonating at its own target means it
+                    // reached the ground without being shot down in flight.
+                    if color == RED {
+                        unintercepted_impacts.push(bullet.position);
+                    }
+                }
             }
-            if is_key_pressed(KeyCode::R) && is_key_down(KeyCode::LeftControl) {
-                self.difficulty.reset();
+        }
+        for impact in unintercepted_impacts {
+            self.destroy_nearest_base(impact);
+        }
+
+        if self.bases.is_empty() {
+            record_score(self.score);
+            stop_theme_music();
+            self.state = GameState::GameOver;
+            return;
+        }
+
+        self.collect_explosions();
+        self.update_explosions();
+
+        draw_sub_text(&format!("Round: {}  Score: {}", self.difficulty.get_round(), self.score), 10., 20.);
+
+        if self.missiles_spawned >= self.difficulty.get_missile_rounds()
+            && self.bullets.is_empty()
+            && self.explosions.is_empty()
+        {
+            let ammo_bonus: u32 = self.bases.iter().map(|base| base.ammo * 5).sum();
+            self.score += ammo_bonus;
+            record_score(self.score);
+            self.difficulty.increase_difficulty();
+            self.missiles_spawned = 0;
+            self.resupply_bases();
+        }
+    }
+
+    fn draw_bullets(&mut self) {
+        // move and draw bullets that haven't detonated yet
+        for bullet in &mut self.bullets {
+            if !bullet.exploding && !bullet.exploded {
+                bullet.position += bullet.velocity * get_frame_time();
+                draw_circle(bullet.position.x, bullet.position.y, 5., bullet.color);
+                draw_line(bullet.source.x, bullet.source.y, bullet.position.x, bullet.position.y, 1., bullet.color);
             }
-            if is_key_pressed(KeyCode::I) && is_key_down(KeyCode::LeftControl) {
-                self.difficulty.increase_difficulty();
+        }
+    }
+
+    /// Moves freshly detonated bullets' explosions into the active list and
+    /// drops them from `bullets`.
+    fn collect_explosions(&mut self) {
+        for bullet in &mut self.bullets {
+            if bullet.exploding {
+                if let Some(explosion) = bullet.explosion {
+                    self.explosions.push(ActiveExplosion::new(explosion));
+                }
+                bullet.exploding = false;
+                bullet.exploded = true;
             }
-            if is_key_pressed(KeyCode::F11) {
-                self.toggle_fullscreen();
+        }
+        self.bullets.retain(|bullet| !bullet.exploded);
+    }
+
+    /// Draws every active explosion's particle burst, chain-detonates any
+    /// bullet caught inside its current blast radius, and retires expired
+    /// explosions.
+    fn update_explosions(&mut self) {
+        for explosion in &mut self.explosions {
+            let radius = explosion.data.current_radius();
+            explosion.emitter.draw(explosion.data.position);
+
+            for bullet in &mut self.bullets {
+                if !bullet.exploding
+                    && !bullet.exploded
+                    && (bullet.position - explosion.data.position).length() < radius
+                {
+                    let color = bullet.color;
+                    if bullet.explode(color, self.difficulty.get_explosion_size(), Duration::from_secs(1), false) {
+                        play_explosion_sound();
+                        if color == RED {
+                            self.score += 10 * self.difficulty.get_round();
+                        }
+                    }
+                }
             }
+        }
+
+        self.explosions.retain(|explosion| !explosion.data.is_expired());
+        self.collect_explosions();
+    }
+
+    async fn draw_paused(&mut self) {
+        if self.starfield {
+            draw_starfield();
+        } else {
+            clear_background(BLACK);
+        }
 
-            next_frame().await;
+        // Keep the frozen battlefield visible behind the pause banner.
+        self.draw_bases();
+        for bullet in &self.bullets {
+            draw_circle(bullet.position.x, bullet.position.y, 5., bullet.color);
+            draw_line(bullet.source.x, bullet.source.y, bullet.position.x, bullet.position.y, 1., bullet.color);
+        }
+        for explosion in &mut self.explosions {
+            explosion.emitter.draw(explosion.data.position);
+        }
+
+        if (is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape)) && !self.escape_consumed {
+            self.state = GameState::Playing;
+            return;
+        }
+
+        draw_main_text("Paused", screen_width() / 2. - 64., screen_height() / 2.);
+        draw_sub_text("Press P or Escape to Resume", screen_width() / 2. - 158., screen_height() / 2. + 30.);
+    }
+
+    async fn draw_game_over(&mut self) {
+        clear_background(BLACK);
+
+        draw_main_text("Game Over", screen_width() / 2. - 90., screen_height() / 2. - 50.);
+        draw_sub_text(
+            &format!("Round Reached: {}  Score: {}", self.difficulty.get_round(), self.score),
+            screen_width() / 2. - 90.,
+            screen_height() / 2.,
+        );
+        draw_sub_text(
+            &format!("High Score: {}", get_high_score()),
+            screen_width() / 2. - 90.,
+            screen_height() / 2. + 15.,
+        );
+        draw_sub_text("Press Space for Main Menu", screen_width() / 2. - 158., screen_height() / 2. + 45.);
+
+        if is_key_pressed(KeyCode::Space) {
+            self.difficulty.reset();
+            self.bullets.clear();
+            self.explosions.clear();
+            self.state = GameState::MainMenu;
         }
-        self.started = true;        
     }
-    
+
     async fn process_input(&mut self) {
+        self.escape_consumed = false;
+
         if is_key_pressed(KeyCode::F11) {
             self.toggle_fullscreen();
         }
         if is_key_pressed(KeyCode::Escape) && self.fullscreen {
             self.toggle_fullscreen();
+            // The same Escape press must not also pause/resume the game below.
+            self.escape_consumed = true;
         }
 
         // ctrl + R to reset the game
         if is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::R) {
             self.difficulty.reset();
         }
-        
+
         if is_key_pressed(KeyCode::F12) {
             self.grid = !self.grid;
         }
+
+        if is_key_pressed(KeyCode::F10) {
+            self.starfield = !self.starfield;
+        }
     }
-    
+
     #[cfg(debug_assertions)]
     async fn draw_grid(&mut self){
         for i in (0..screen_width() as i32).step_by(10) {
@@ -197,7 +725,7 @@ impl Game {
         }
         draw_line(screen_width() / 2., 0., screen_width() / 2., screen_height(), 2., RED);
         draw_line(0., screen_height() / 2., screen_width(), screen_height() / 2., 2., RED);
-    
+
     }
     #[cfg(not(debug_assertions))]
     async fn draw_grid(&mut self){}
@@ -232,9 +760,9 @@ impl Difficulty {
     }
 
     pub fn increase_difficulty(&mut self) {
-        self.missile_spawn_rate -= 0.1 * self.round as f32;
+        self.missile_spawn_rate = (self.missile_spawn_rate - 0.1 * self.round as f32).max(0.2);
         self.missile_speed += 1.1 * self.round as f32;
-        self.missile_rounds += 1 * self.round;
+        self.missile_rounds += self.round;
         self.round += 1;
         self.explosion_size -= 0.01 * self.round as f32;
     }
@@ -268,134 +796,31 @@ impl Default for Difficulty {
 
 #[macroquad::main("Space Command")]
 async fn main() {
-    let font = include_bytes!("../assets/fonts/Geoplace-Bold.ttf");
-    let mut game = Game::new(font).await;
-    let mut bullets: Vec<Bullet> = Vec::new();
-    let mut time_since_spawn = 0.;
-    let mut difficulty = Difficulty::new();
-    let mut debug_grid = false;
-    loop {
-        if !game.started {
-            // Clear the screen
-            clear_background(BLACK);
-            set_default_camera();
-            // Show Controls
-            let font = &game.font.clone();
-            let main_text_params = TextParams {
-                font_size: 20,
-                font_scale: 1.0,
-                font_scale_aspect: 1.0,
-                font: Some(font),
-                color: WHITE,
-                rotation: 0.,
-            };
-            let sub_text_params = TextParams {
-                font_size: 15,
-                font_scale: 1.0,
-                font_scale_aspect: 1.0,
-                font: Some(font),
-                color: GRAY,
-                rotation: 0.,
-            };
-            loop {
-                if is_key_pressed(KeyCode::F12) {
-                    debug_grid = !debug_grid;
-                }
-                if debug_grid {
-                    game.draw_grid().await;
-                }
-                else {
-                    clear_background(BLACK);
-                }
-                draw_text_ex("Space Command", screen_width() / 2. - 128., screen_height() / 2. - 50., main_text_params.clone());
-                draw_text_ex("Press Space to Start", screen_width() / 2. - 158., screen_height(), main_text_params.clone());
-                draw_text_ex("Press Ctrl + R to Reset", screen_width() / 2. - 128., screen_height() - 50., sub_text_params.clone());
-
-                if is_key_pressed(KeyCode::Space) {
-                    break;
-                }
-                if is_key_pressed(KeyCode::R) && is_key_down(KeyCode::LeftControl) {
-                    difficulty.reset();
-                }
-                if is_key_pressed(KeyCode::I) && is_key_down(KeyCode::LeftControl) {
-                    difficulty.increase_difficulty();
-                }
-                if is_key_pressed(KeyCode::F11) {
-                    game.toggle_fullscreen();
-                }
-
-                next_frame().await;
-            }
-            game.started = true;
-        }
-        // Clear the screen
-        clear_background(BLACK);
-
-
-        if is_key_pressed(KeyCode::F11) {
-            game.toggle_fullscreen();
-        }
-        if is_key_pressed(KeyCode::Escape) && game.fullscreen {
-            game.toggle_fullscreen();
-        }
-
-        // ctrl + R to reset the game
-        if is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::R) {
-            difficulty.reset();
-        }
+    let resources_loading = start_coroutine(async move {
+        let resources = Resources::load().await.expect("Failed to load resources");
+        storage::store(resources);
+    });
 
+    while !resources_loading.is_done() {
         clear_background(BLACK);
+        draw_text("Loading...", screen_width() / 2. - 50., screen_height() / 2., 30., WHITE);
+        next_frame().await;
+    }
 
-        // every 3 seconds add a new bullet
+    storage::store(HighScore(load_high_score()));
 
-        time_since_spawn += get_frame_time();
-        if time_since_spawn > 1. {
-            let source = vec2(rand::gen_range(0., screen_height()), 0.);
-            let target = vec2(rand::gen_range(0., screen_width()), screen_height());
-            let velocity = (target - source).normalize() * difficulty.get_missile_speed();
-            let bullet = Bullet::new(source, target, velocity);
-            bullets.push(bullet);
-            time_since_spawn = 0.;
-        }
+    let mut game = Game::new().await;
 
-        // Draw bullets
-        for bullet in &mut bullets {
-            // if bullet is exploding, draw the explosion
-            if bullet.exploding {
-                let explosion = bullet.explosion;
-                if let Some(explosion) = explosion {
-                    draw_circle(explosion.position.x, explosion.position.y, explosion.size, explosion.color);
-                }
-                bullet.exploding = false;
-                bullet.exploded = true;
-            }
-
-            // move bullet
-            bullet.position += bullet.velocity * get_frame_time();
-            draw_circle(bullet.position.x, bullet.position.y, 5., bullet.color);
-        }
-
-        // remove bullets that have reached their target
-        bullets.retain(|bullet| {
-            !bullet.exploded
-        });
-
-        // draw bullets, poly line leading from source to position
-        for bullet in &bullets {
-            draw_line(bullet.source.x, bullet.source.y, bullet.position.x, bullet.position.y, 1., bullet.color);
-        }
+    loop {
+        game.process_input().await;
 
-        // check if any bullets have reached their target
-        for bullet in &mut bullets {
-            if bullet.at_target() {
-                bullet.explode(RED, 50., Duration::from_secs(1), false);
-            }
+        match game.state {
+            GameState::MainMenu => game.draw_main_menu().await,
+            GameState::Playing => game.update_playing().await,
+            GameState::Paused => game.draw_paused().await,
+            GameState::GameOver => game.draw_game_over().await,
         }
 
-        bullets.retain(|bullet| {
-            !bullet.exploded
-        });
-
         next_frame().await
     }
-}
\ No newline at end of file
+}